@@ -1,11 +1,23 @@
 use image::{ImageBuffer, RgbImage};
 use rand::prelude::*;
 use rand_distr::{Exp, LogNormal, Normal, StandardNormal};
+use rayon::prelude::*;
 use scarlet::prelude::*;
 use scarlet::colors::CIELABColor;
+use serde::Deserialize;
 
 use std::f64::consts::PI;
 
+// How streams pick their color.
+#[derive(Debug, Clone, Copy, Deserialize)]
+enum ColorMode {
+    // Each stream's color is a per-faucet Gaussian, as before.
+    FaucetGaussian,
+    // Colors are pre-generated by walking a 3D Hilbert curve through the
+    // CIELAB cube, then handed out to streams in shuffled order.
+    HilbertGamut,
+}
+
 // Faucets create streams, streams move according to forces
 #[derive(Clone, Copy, Default)]
 struct ColorOffset {
@@ -79,17 +91,6 @@ impl Position {
             y: self.y * ratio,
         }
     }
-    fn to_pixels(&self, size: u32) -> (Option<usize>, Option<usize>) {
-        let f_size = size as f64;
-        let to_pixel = &|f: f64| {
-            if f > 0.0 && f < f_size {
-                Some(f as usize)
-            } else {
-                None
-            }
-        };
-        (to_pixel(self.x), to_pixel(self.y))
-    }
     fn length(&self) -> f64 {
         (self.x.powi(2) + self.y.powi(2)).sqrt()
     }
@@ -122,18 +123,138 @@ enum ForceKind {
     Inward,
     Outward,
     Linear(Position),
+    // Fractal Perlin turbulence; carries its own shuffled permutation table.
+    Turbulence(Vec<u8>),
 }
 impl ForceKind {
     fn sample<R: Rng>(rng: &mut R) -> Self {
         let main: f64 = rng.gen();
-        if main < 0.333 {
+        if main < 0.25 {
             ForceKind::Inward
-        } else if main < 0.666 {
+        } else if main < 0.5 {
             ForceKind::Outward
-        } else {
+        } else if main < 0.75 {
             ForceKind::Linear(Position::sample_direction(rng))
+        } else {
+            ForceKind::Turbulence(sample_permutation(rng))
+        }
+    }
+}
+
+// A shuffled 0..256 permutation, duplicated so corner lookups can add an
+// index without wrapping.
+fn sample_permutation<R: Rng>(rng: &mut R) -> Vec<u8> {
+    let mut perm: Vec<u8> = (0..=255).collect();
+    perm.shuffle(rng);
+    perm.iter().chain(perm.iter()).copied().collect()
+}
+
+// Pseudo-random unit gradient selected by `hash`, dotted with the cell-local offset.
+fn grad(hash: u8, x: f64, y: f64) -> f64 {
+    let angle = (hash as f64 / 256.0) * 2.0 * PI;
+    angle.cos() * x + angle.sin() * y
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + t * (b - a)
+}
+
+// Classic gradient (Perlin) noise over the unit cell containing (x, y).
+fn noise(perm: &[u8], x: f64, y: f64) -> f64 {
+    let xi = x.floor() as i64 as usize & 255;
+    let yi = y.floor() as i64 as usize & 255;
+    let xf = x - x.floor();
+    let yf = y - y.floor();
+    let u = fade(xf);
+    let v = fade(yf);
+
+    let aa = perm[perm[xi] as usize + yi];
+    let ab = perm[perm[xi] as usize + yi + 1];
+    let ba = perm[perm[xi + 1] as usize + yi];
+    let bb = perm[perm[xi + 1] as usize + yi + 1];
+
+    let x1 = lerp(grad(aa, xf, yf), grad(ba, xf - 1.0, yf), u);
+    let x2 = lerp(grad(ab, xf, yf - 1.0), grad(bb, xf - 1.0, yf - 1.0), u);
+    lerp(x1, x2, v)
+}
+
+// Fractal turbulence: noise summed across octaves of doubling frequency and
+// halving amplitude, `f_i = base_freq * 2^i`.
+fn turbulence(perm: &[u8], x: f64, y: f64, base_freq: f64, octaves: u32) -> f64 {
+    (0..octaves)
+        .map(|i| {
+            let freq = base_freq * 2f64.powi(i as i32);
+            noise(perm, x * freq, y * freq) / 2f64.powi(i as i32)
+        })
+        .sum()
+}
+
+// Maps a 1D index `d` along a 3D Hilbert curve of the given `order`
+// (covering a `2^order`-per-side cube) to `(x, y, z)` coordinates, via
+// Skilling's transpose-then-Gray-decode algorithm: `d`'s `3*order` bits are
+// first scattered 3-bits-per-level (most significant level first) into one
+// bit-plane per axis, then Gray-decoded and rotated back into true
+// coordinates.
+fn hilbert_d2xyz(order: u32, d: u64) -> (u32, u32, u32) {
+    let mut x = [0u64; 3];
+    for level in 0..order {
+        let shift = 3 * (order - 1 - level);
+        let bits = (d >> shift) & 0b111;
+        for (axis, value) in x.iter_mut().enumerate() {
+            // Skilling's `TransposeToAxes` expects the group's most
+            // significant bit at axis 0, not its least significant bit.
+            if (bits >> (2 - axis)) & 1 == 1 {
+                *value |= 1 << (order - 1 - level);
+            }
         }
     }
+    // Gray decode.
+    let t = x[2] >> 1;
+    x[2] ^= x[1];
+    x[1] ^= x[0];
+    x[0] ^= t;
+    // Undo excess work: rotate/invert each bit-plane into true axes.
+    let mut q = 2u64;
+    while q != (1u64 << order) {
+        let p = q - 1;
+        for i in (0..3).rev() {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q <<= 1;
+    }
+    (x[0] as u32, x[1] as u32, x[2] as u32)
+}
+
+// Walks the 3D Hilbert curve to tile the space of displayable offsets: index
+// `d` out of `total_cells` maps to a cell center in the cube
+// `[-color_cap, color_cap]^3`. Streams then decay by scaling this offset
+// down from its corner before `ColorOffset::to_rgb` squashes it, so the
+// offset (not the final Lab color) is what has to vary evenly — mapping
+// cells straight into this cube, rather than inverting `to_rgb`'s sigmoid,
+// keeps every cell's length within `color_cap * sqrt(3)` instead of up to
+// two orders of magnitude over `color_cap`. Cells near the cube's corners still exceed
+// `color_cap` in length (corner length is `color_cap * sqrt(3)`) and get
+// re-compressed by `to_rgb`'s clamp, so the tiling is even in offset space
+// but only approximately even in the final Lab gamut.
+fn hilbert_gamut_color(order: u32, d: u64, color_cap: f64) -> ColorOffset {
+    let (x, y, z) = hilbert_d2xyz(order, d);
+    let n = (1u32 << order) as f64;
+    let to_offset = |v: u32| ((v as f64 + 0.5) / n * 2.0 - 1.0) * color_cap;
+    ColorOffset {
+        r: to_offset(x),
+        g: to_offset(y),
+        b: to_offset(z),
+    }
 }
 
 // Lognormal force strength, spread distribution
@@ -143,17 +264,41 @@ struct Force {
     strength: f64,
     position: Position,
     spread: f64,
+    base_freq: f64,
+    octaves: u32,
+    // Drift per unit of `dt`, applied only in animation mode; a still image
+    // never moves its forces.
+    velocity: Position,
+    // Rotation rate per unit of `dt` of `ForceKind::Linear`'s direction;
+    // `None` for other kinds, which have no direction to spin.
+    angular_rate: Option<f64>,
 }
 impl Force {
     fn apply(&self, target: Position) -> Position {
+        // Turbulence is a global river-like flow field rather than something
+        // radiating from `self.position`, so unlike the other kinds it isn't
+        // gated by the localized Gaussian `push` envelope below; it's driven
+        // by `self.strength` directly.
+        if let ForceKind::Turbulence(perm) = &self.kind {
+            let eps = 1.0;
+            let n = |x: f64, y: f64| turbulence(perm, x, y, self.base_freq, self.octaves);
+            let n0 = n(target.x, target.y);
+            let dn_dx = (n(target.x + eps, target.y) - n0) / eps;
+            let dn_dy = (n(target.x, target.y + eps) - n0) / eps;
+            // Rotate the noise gradient 90 degrees for a swirling,
+            // near-divergence-free flow.
+            let swirl = Position { x: -dn_dy, y: dn_dx };
+            return swirl.scale(self.strength / swirl.length().max(1e-9));
+        }
         let offset = target.add(self.position.scale(-1.0));
         let distance = offset.length();
         let num_devs = distance / self.spread;
         let push = self.strength/self.spread * (-num_devs.powi(2) / 2.0).exp();
-        let dir: Position = match self.kind {
+        let dir: Position = match &self.kind {
             ForceKind::Inward => offset.scale(-1.0 / distance),
             ForceKind::Outward => offset.scale(1.0 / distance),
-            ForceKind::Linear(dir) => dir,
+            ForceKind::Linear(dir) => *dir,
+            ForceKind::Turbulence(_) => unreachable!("handled above"),
         };
         dir.scale(push)
     }
@@ -166,55 +311,129 @@ struct Params {
     num_forces: usize,
     force_strength_dist: LogNormal<f64>,
     force_spread_dist: LogNormal<f64>,
+    force_turbulence_freq_dist: LogNormal<f64>,
+    force_turbulence_octaves: u32,
+    force_velocity_dist: Normal<f64>,
+    force_angular_rate_dist: Normal<f64>,
     num_faucets: usize,
     faucet_color_center_dist: Normal<f64>,
     faucet_color_spread_dist: Exp<f64>,
     faucet_position_spread_dist: Exp<f64>,
     faucet_velocity_spread_dist: Exp<f64>,
+    color_mode: ColorMode,
     num_streams: usize,
     decay_dist: Exp<f64>,
     max_decay_factor: f64,
     velocity_cap: f64,
     color_cap: f64,
+    filter_radius: f64,
+    filter_alpha: f64,
+    // Animation mode: number of frames to render (1 renders a still image)
+    // and the timestep each frame advances forces by.
+    num_frames: usize,
+    dt: f64,
 }
-fn draw(params: Params) -> RgbImage {
-    let mut rng = StdRng::seed_from_u64(params.seed);
-    // Create forces
-    let forces: Vec<Force> = (0..params.num_forces)
+
+// The reconstruction filter `splat` splats through: a Gaussian falloff out
+// to `radius`, zeroed out at the edge so the filter has compact support.
+struct Filter {
+    radius: f64,
+    alpha: f64,
+}
+
+// Splats `color` at the fractional pixel position `(fx, fy)` into every
+// pixel within `filter.radius`, weighted by a Gaussian falloff that's
+// zeroed out at the radius edge so the filter has compact support.
+fn splat(
+    grid: &mut [Vec<ColorOffset>],
+    weights: &mut [Vec<f64>],
+    size: u32,
+    fx: f64,
+    fy: f64,
+    color: ColorOffset,
+    filter: Filter,
+) {
+    let Filter { radius, alpha } = filter;
+    let max_coord = size as f64 - 1.0;
+    // The last pixel's center sits at `size - 0.5`, so only samples more
+    // than `radius` past that reach no pixel.
+    let far_edge = size as f64 - 0.5;
+    if fx + radius < 0.0 || fx - radius > far_edge || fy + radius < 0.0 || fy - radius > far_edge
+    {
+        return;
+    }
+    let min_x = (fx - radius).floor().clamp(0.0, max_coord) as usize;
+    let max_x = (fx + radius).ceil().clamp(0.0, max_coord) as usize;
+    let min_y = (fy - radius).floor().clamp(0.0, max_coord) as usize;
+    let max_y = (fy + radius).ceil().clamp(0.0, max_coord) as usize;
+    let edge_weight = (-alpha * radius.powi(2)).exp();
+    for px in min_x..=max_x {
+        for py in min_y..=max_y {
+            let dx = px as f64 + 0.5 - fx;
+            let dy = py as f64 + 0.5 - fy;
+            let d2 = dx.powi(2) + dy.powi(2);
+            let w = ((-alpha * d2).exp() - edge_weight).max(0.0);
+            if w > 0.0 {
+                grid[px][py] = grid[px][py].add(color.scale(w));
+                weights[px][py] += w;
+            }
+        }
+    }
+}
+
+fn sample_forces(rng: &mut StdRng, params: &Params) -> Vec<Force> {
+    (0..params.num_forces)
         .map(|_| {
-            let position = Position::sample(&mut rng, params.size);
-            let kind = ForceKind::sample(&mut rng);
-            let strength = params.force_strength_dist.sample(&mut rng);
-            let spread = params.force_spread_dist.sample(&mut rng);
+            let position = Position::sample(rng, params.size);
+            let kind = ForceKind::sample(rng);
+            let strength = params.force_strength_dist.sample(rng);
+            let spread = params.force_spread_dist.sample(rng);
+            let base_freq = params.force_turbulence_freq_dist.sample(rng);
+            let octaves = params.force_turbulence_octaves;
+            let velocity = Position {
+                x: params.force_velocity_dist.sample(rng),
+                y: params.force_velocity_dist.sample(rng),
+            };
+            // Only `Linear` forces have a direction to spin.
+            let angular_rate = match kind {
+                ForceKind::Linear(_) => Some(params.force_angular_rate_dist.sample(rng)),
+                _ => None,
+            };
             Force {
                 kind,
                 strength,
                 spread,
                 position,
+                base_freq,
+                octaves,
+                velocity,
+                angular_rate,
             }
         })
-        .collect();
-    // Create faucets
-    let faucets: Vec<Faucet> = (0..params.num_faucets)
+        .collect()
+}
+
+fn sample_faucets(rng: &mut StdRng, params: &Params) -> Vec<Faucet> {
+    (0..params.num_faucets)
         .map(|_| {
             let color_center = ColorOffset {
-                r: params.faucet_color_center_dist.sample(&mut rng),
-                g: params.faucet_color_center_dist.sample(&mut rng),
-                b: params.faucet_color_center_dist.sample(&mut rng),
+                r: params.faucet_color_center_dist.sample(rng),
+                g: params.faucet_color_center_dist.sample(rng),
+                b: params.faucet_color_center_dist.sample(rng),
             };
             let color_spreads = ColorOffset {
-                r: params.faucet_color_spread_dist.sample(&mut rng),
-                g: params.faucet_color_spread_dist.sample(&mut rng),
-                b: params.faucet_color_spread_dist.sample(&mut rng),
+                r: params.faucet_color_spread_dist.sample(rng),
+                g: params.faucet_color_spread_dist.sample(rng),
+                b: params.faucet_color_spread_dist.sample(rng),
             };
-            let position = Position::sample(&mut rng, params.size);
+            let position = Position::sample(rng, params.size);
             let position_spreads = Position {
-                x: params.faucet_position_spread_dist.sample(&mut rng),
-                y: params.faucet_position_spread_dist.sample(&mut rng),
+                x: params.faucet_position_spread_dist.sample(rng),
+                y: params.faucet_position_spread_dist.sample(rng),
             };
             let velocity_spreads = Position {
-                x: params.faucet_velocity_spread_dist.sample(&mut rng),
-                y: params.faucet_velocity_spread_dist.sample(&mut rng),
+                x: params.faucet_velocity_spread_dist.sample(rng),
+                y: params.faucet_velocity_spread_dist.sample(rng),
             };
             Faucet {
                 color_center,
@@ -224,19 +443,54 @@ fn draw(params: Params) -> RgbImage {
                 velocity_spreads,
             }
         })
-        .collect();
-    // Sample streams
-    let streams: Vec<Stream> = (0..params.num_streams)
-        .map(|_| {
+        .collect()
+}
+
+// For HilbertGamut, pre-generates one color per stream by walking the curve
+// evenly, then shuffles the assignment so spatial placement (still driven
+// by faucet sampling in `sample_streams`) stays independent of hue order.
+fn sample_hilbert_colors(rng: &mut StdRng, params: &Params) -> Option<Vec<ColorOffset>> {
+    match params.color_mode {
+        ColorMode::FaucetGaussian => None,
+        ColorMode::HilbertGamut => {
+            let order = 8;
+            let total_cells = 1u64 << (3 * order);
+            let num_streams = params.num_streams.max(1) as u64;
+            let mut colors: Vec<ColorOffset> = (0..params.num_streams)
+                .map(|i| {
+                    let d = (i as u64 * total_cells) / num_streams;
+                    hilbert_gamut_color(order, d, params.color_cap)
+                })
+                .collect();
+            colors.shuffle(rng);
+            Some(colors)
+        }
+    }
+}
+
+fn sample_streams(
+    rng: &mut StdRng,
+    params: &Params,
+    faucets: &[Faucet],
+    hilbert_colors: &Option<Vec<ColorOffset>>,
+) -> Vec<Stream> {
+    (0..params.num_streams)
+        .map(|i| {
             let faucet_index = rng.gen_range(0..params.num_faucets);
             let faucet = &faucets[faucet_index];
-            let color = ColorOffset {
-                r: faucet.color_center.r
-                    + faucet.color_spreads.r * rng.sample::<f64, StandardNormal>(StandardNormal),
-                g: faucet.color_center.g
-                    + faucet.color_spreads.g * rng.sample::<f64, StandardNormal>(StandardNormal),
-                b: faucet.color_center.b
-                    + faucet.color_spreads.b * rng.sample::<f64, StandardNormal>(StandardNormal),
+            let color = match hilbert_colors {
+                Some(colors) => colors[i],
+                None => ColorOffset {
+                    r: faucet.color_center.r
+                        + faucet.color_spreads.r
+                            * rng.sample::<f64, StandardNormal>(StandardNormal),
+                    g: faucet.color_center.g
+                        + faucet.color_spreads.g
+                            * rng.sample::<f64, StandardNormal>(StandardNormal),
+                    b: faucet.color_center.b
+                        + faucet.color_spreads.b
+                            * rng.sample::<f64, StandardNormal>(StandardNormal),
+                },
             };
             let position = Position {
                 x: faucet.position.x
@@ -248,7 +502,7 @@ fn draw(params: Params) -> RgbImage {
                 x: faucet.velocity_spreads.x * rng.sample::<f64, StandardNormal>(StandardNormal),
                 y: faucet.velocity_spreads.y * rng.sample::<f64, StandardNormal>(StandardNormal),
             };
-            let decay_rate = params.decay_dist.sample(&mut rng);
+            let decay_rate = params.decay_dist.sample(rng);
             Stream {
                 color,
                 position,
@@ -256,51 +510,141 @@ fn draw(params: Params) -> RgbImage {
                 decay_rate,
             }
         })
-        .collect();
-    // Create image to draw into - x then y.
-    let mut grid: Vec<Vec<ColorOffset>> =
-        vec![vec![Default::default(); params.size as usize]; params.size as usize];
-    // Draw streams
-    for mut stream in streams {
-        let max_age = (params.max_decay_factor / stream.decay_rate) as u64;
-        let mut age = 0;
-        while age < max_age
-            && !(stream.position.x < -(params.size as f64))
-            && !(stream.position.x > 2.0 * params.size as f64)
-            && !(stream.position.y < -(params.size as f64))
-            && !(stream.position.y > 2.0 * params.size as f64)
-        {
-            let old_age = age;
-            // Draw connecting line
-            let norm = stream.velocity.x.abs().max(stream.velocity.y.abs());
-            let base_offset = stream.velocity.scale(1.0 / norm as f64);
-            let num_pixels = norm as usize;
-            for i in 1..=num_pixels {
-                let offset = base_offset.scale(i as f64);
-                let current_position = stream.position.add(offset);
-                if let (Some(pixel_x), Some(pixel_y)) = current_position.to_pixels(params.size) {
+        .collect()
+}
+
+// Advances each force's position (and, for `Linear` forces, direction) by
+// one timestep, for the animation mode.
+fn advance_forces(forces: &mut [Force], dt: f64) {
+    for force in forces.iter_mut() {
+        force.position = force.position.add(force.velocity.scale(dt));
+        if let (ForceKind::Linear(dir), Some(rate)) = (&mut force.kind, force.angular_rate) {
+            let angle = rate * dt;
+            let (sin, cos) = angle.sin_cos();
+            *dir = Position {
+                x: dir.x * cos - dir.y * sin,
+                y: dir.x * sin + dir.y * cos,
+            };
+        }
+    }
+}
+
+fn draw(params: Params) -> RgbImage {
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let forces = sample_forces(&mut rng, &params);
+    let faucets = sample_faucets(&mut rng, &params);
+    let hilbert_colors = sample_hilbert_colors(&mut rng, &params);
+    let streams = sample_streams(&mut rng, &params, &faucets, &hilbert_colors);
+    integrate_streams(streams, &forces, &params)
+}
+
+// Renders a flow-field animation: forces drift/spin frame to frame while
+// faucets stay put, and each frame resamples fresh streams from a
+// per-frame-derived seed so the flow is continuous but the streams aren't.
+fn animate(params: Params) {
+    let mut rng = StdRng::seed_from_u64(params.seed);
+    let mut forces = sample_forces(&mut rng, &params);
+    let faucets = sample_faucets(&mut rng, &params);
+    let hilbert_colors = sample_hilbert_colors(&mut rng, &params);
+    std::fs::create_dir_all("frames").expect("Created frames directory");
+    for frame in 0..params.num_frames {
+        let frame_seed = params.seed.wrapping_add(frame as u64 + 1);
+        let mut frame_rng = StdRng::seed_from_u64(frame_seed);
+        let streams = sample_streams(&mut frame_rng, &params, &faucets, &hilbert_colors);
+        let image = integrate_streams(streams, &forces, &params);
+        let filename = format!("frames/frame-{:04}.png", frame);
+        image.save(&filename).expect("Saved");
+        advance_forces(&mut forces, params.dt);
+    }
+}
+
+fn integrate_streams(streams: Vec<Stream>, forces: &[Force], params: &Params) -> RgbImage {
+    // Empty grid/weights buffers, one pair per rayon worker.
+    let empty_buffers = || {
+        (
+            vec![vec![ColorOffset::default(); params.size as usize]; params.size as usize],
+            vec![vec![0.0; params.size as usize]; params.size as usize],
+        )
+    };
+    // Draw streams: partition across threads, each integrating its slice of
+    // streams into a local grid/weights pair, then reduce pairwise with
+    // element-wise `ColorOffset::add`. Float addition isn't associative and
+    // rayon's work-stealing varies the fold/reduce grouping between runs, so
+    // per-pixel sums (and thus exact pixel values) aren't guaranteed to be
+    // bit-identical across thread counts, only visually stable.
+    let (mut grid, weights) = streams
+        .into_par_iter()
+        .fold(empty_buffers, |(mut grid, mut weights), mut stream| {
+            let max_age = (params.max_decay_factor / stream.decay_rate) as u64;
+            let mut age = 0;
+            while age < max_age
+                && !(stream.position.x < -(params.size as f64))
+                && !(stream.position.x > 2.0 * params.size as f64)
+                && !(stream.position.y < -(params.size as f64))
+                && !(stream.position.y > 2.0 * params.size as f64)
+            {
+                let old_age = age;
+                // Draw connecting line
+                let norm = stream.velocity.x.abs().max(stream.velocity.y.abs());
+                let base_offset = stream.velocity.scale(1.0 / norm as f64);
+                let num_pixels = norm as usize;
+                for i in 1..=num_pixels {
+                    let offset = base_offset.scale(i as f64);
+                    let current_position = stream.position.add(offset);
                     let intensity = (-stream.decay_rate * age as f64).exp();
                     let color = stream.color.scale(intensity);
-                    grid[pixel_x][pixel_y] = grid[pixel_x][pixel_y].add(color);
+                    splat(
+                        &mut grid,
+                        &mut weights,
+                        params.size,
+                        current_position.x,
+                        current_position.y,
+                        color,
+                        Filter {
+                            radius: params.filter_radius,
+                            alpha: params.filter_alpha,
+                        },
+                    );
+                    age += 1;
+                }
+                // Update position
+                stream.position = stream.position.add(stream.velocity);
+                // Update age at least a minimum amount
+                if age == old_age {
+                    age += 1;
+                }
+                // Update velocity via forces
+                for force in forces {
+                    let velocity_update = force.apply(stream.position);
+                    stream.velocity = stream.velocity.add(velocity_update);
+                }
+                // Cap velocity
+                if stream.velocity.length() > params.velocity_cap {
+                    stream.velocity = stream
+                        .velocity
+                        .scale(params.velocity_cap / stream.velocity.length())
                 }
-                age += 1;
             }
-            // Update position
-            stream.position = stream.position.add(stream.velocity);
-            // Update age at least a minimum amount
-            if age == old_age {
-                age += 1;
+            (grid, weights)
+        })
+        .reduce(empty_buffers, |(mut grid_a, mut weights_a), (grid_b, weights_b)| {
+            for (row_a, row_b) in grid_a.iter_mut().zip(grid_b) {
+                for (color_a, color_b) in row_a.iter_mut().zip(row_b) {
+                    *color_a = color_a.add(color_b);
+                }
             }
-            // Update velocity via forces
-            for force in &forces {
-                let velocity_update = force.apply(stream.position);
-                stream.velocity = stream.velocity.add(velocity_update);
+            for (row_a, row_b) in weights_a.iter_mut().zip(weights_b) {
+                for (weight_a, weight_b) in row_a.iter_mut().zip(row_b) {
+                    *weight_a += weight_b;
+                }
             }
-            // Cap velocity
-            if stream.velocity.length() > params.velocity_cap {
-                stream.velocity = stream
-                    .velocity
-                    .scale(params.velocity_cap / stream.velocity.length())
+            (grid_a, weights_a)
+        });
+    // Normalize splatted colors by their accumulated filter weight.
+    for (row, weight_row) in grid.iter_mut().zip(weights.iter()) {
+        for (color, weight) in row.iter_mut().zip(weight_row.iter()) {
+            if *weight > 0.0 {
+                *color = color.scale(1.0 / weight);
             }
         }
     }
@@ -318,29 +662,160 @@ fn log_dist(center: f64, mult_spread: f64) -> LogNormal<f64> {
     LogNormal::new(center.ln(), mult_spread.ln()).expect("Valid dist")
 }
 
+// Human-meaningful knobs that drive `Params`, loadable from a TOML file.
+// `LogNormal`/`Normal`/`Exp` aren't serde-friendly, so this holds the plain
+// numbers and reconstructs the distributions in `build`.
+#[derive(Debug, Deserialize)]
+struct ParamsConfig {
+    seed: u64,
+    num_forces: usize,
+    force_strength_center: f64,
+    force_strength_mult_spread: f64,
+    force_spread_center: f64,
+    force_spread_mult_spread: f64,
+    force_turbulence_freq_center: f64,
+    force_turbulence_freq_mult_spread: f64,
+    force_turbulence_octaves: u32,
+    force_velocity_spread: f64,
+    force_angular_rate_spread: f64,
+    num_faucets: usize,
+    faucet_color_center_spread: f64,
+    faucet_color_spread_mean: f64,
+    faucet_position_spread_mean: f64,
+    faucet_velocity_spread_mean: f64,
+    color_mode: ColorMode,
+    num_streams: usize,
+    decay_mean: f64,
+    max_decay_factor: f64,
+    velocity_cap: f64,
+    color_cap: f64,
+    filter_radius: f64,
+    filter_alpha: f64,
+    num_frames: usize,
+    dt: f64,
+}
+impl ParamsConfig {
+    fn build(self, size: u32) -> Params {
+        Params {
+            size,
+            seed: self.seed,
+            num_forces: self.num_forces,
+            force_spread_dist: log_dist(self.force_spread_center, self.force_spread_mult_spread),
+            force_strength_dist: log_dist(
+                self.force_strength_center,
+                self.force_strength_mult_spread,
+            ),
+            force_turbulence_freq_dist: log_dist(
+                self.force_turbulence_freq_center,
+                self.force_turbulence_freq_mult_spread,
+            ),
+            force_turbulence_octaves: self.force_turbulence_octaves,
+            force_velocity_dist: Normal::new(0.0, self.force_velocity_spread)
+                .expect("Valid dist"),
+            force_angular_rate_dist: Normal::new(0.0, self.force_angular_rate_spread)
+                .expect("Valid dist"),
+            num_faucets: self.num_faucets,
+            faucet_color_center_dist: Normal::new(0.0, self.faucet_color_center_spread)
+                .expect("Valid dist"),
+            faucet_color_spread_dist: Exp::new(1.0 / self.faucet_color_spread_mean)
+                .expect("Valid dist"),
+            faucet_position_spread_dist: Exp::new(1.0 / self.faucet_position_spread_mean)
+                .expect("Valid dist"),
+            faucet_velocity_spread_dist: Exp::new(1.0 / self.faucet_velocity_spread_mean)
+                .expect("Valid dist"),
+            color_mode: self.color_mode,
+            num_streams: self.num_streams,
+            decay_dist: Exp::new(1.0 / self.decay_mean).expect("Valid dist"),
+            max_decay_factor: self.max_decay_factor,
+            velocity_cap: self.velocity_cap,
+            color_cap: self.color_cap,
+            filter_radius: self.filter_radius,
+            filter_alpha: self.filter_alpha,
+            num_frames: self.num_frames,
+            dt: self.dt,
+        }
+    }
+}
+impl Default for ParamsConfig {
+    fn default() -> Self {
+        ParamsConfig {
+            seed: 0,
+            num_forces: 200,
+            force_strength_center: 10.0,
+            force_strength_mult_spread: 2.0,
+            force_spread_center: 200.0,
+            force_spread_mult_spread: 2.0,
+            force_turbulence_freq_center: 0.01,
+            force_turbulence_freq_mult_spread: 2.0,
+            force_turbulence_octaves: 4,
+            force_velocity_spread: 0.0,
+            force_angular_rate_spread: 0.01,
+            num_faucets: 40,
+            faucet_color_center_spread: 0.03,
+            faucet_color_spread_mean: 0.03,
+            faucet_position_spread_mean: 80.0,
+            faucet_velocity_spread_mean: 1.0,
+            color_mode: ColorMode::FaucetGaussian,
+            num_streams: 100000,
+            decay_mean: 0.001,
+            max_decay_factor: 10.0,
+            velocity_cap: 40.0,
+            color_cap: 2.0,
+            filter_radius: 1.5,
+            filter_alpha: 2.0,
+            num_frames: 1,
+            dt: 1.0,
+        }
+    }
+}
+
+// Loads a `ParamsConfig` from the TOML file at `path`, falling back to
+// `ParamsConfig::default()` if no path was given.
+fn load_config(path: Option<&str>) -> ParamsConfig {
+    match path {
+        Some(path) => {
+            let contents = std::fs::read_to_string(path).expect("Config file readable");
+            toml::from_str(&contents).expect("Valid config")
+        }
+        None => ParamsConfig::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every consecutive pair of indices along a Hilbert curve must land on
+    // grid cells that are orthogonally adjacent (Manhattan distance 1); a
+    // bug in the decode can silently produce a bijection that isn't.
+    #[test]
+    fn hilbert_3d_walk_is_contiguous() {
+        let order = 4;
+        let mut prev = hilbert_d2xyz(order, 0);
+        for d in 1..(1u64 << (3 * order)) {
+            let cur = hilbert_d2xyz(order, d);
+            let dist = (prev.0 as i64 - cur.0 as i64).abs()
+                + (prev.1 as i64 - cur.1 as i64).abs()
+                + (prev.2 as i64 - cur.2 as i64).abs();
+            assert_eq!(dist, 1, "step {} -> {} moved by {}", d - 1, d, dist);
+            prev = cur;
+        }
+    }
+}
+
 fn main() {
     let size = 1000;
-    let params = Params {
-        size,
-        seed: 0,
-        num_forces: 200,
-        force_spread_dist: log_dist(200.0, 2.0),
-        force_strength_dist: log_dist(10.0, 2.0),
-        num_faucets: 40,
-        faucet_color_center_dist: Normal::new(0.0, 0.03).expect("Valid dist"),
-        faucet_color_spread_dist: Exp::new(1.0 / 0.03).expect("Valid dist"),
-        faucet_position_spread_dist: Exp::new(1.0 / 80.0).expect("Valid dist"),
-        faucet_velocity_spread_dist: Exp::new(1.0 / 1.0).expect("Valid dist"),
-        num_streams: 100000,
-        decay_dist: Exp::new(size as f64).expect("Valid dist"),
-        max_decay_factor: 10.0,
-        velocity_cap: 40.0,
-        color_cap: 2.0,
-    };
+    let config_path = std::env::args().nth(1);
+    let config = load_config(config_path.as_deref());
+    let params = config.build(size);
     dbg!(&params);
-    let num_entries = std::fs::read_dir(".").expect("Valid").count();
-    let image = draw(params);
-    let filename: String = format!("img-{}-{}.png", num_entries, size);
-    image.save(&filename).expect("Saved");
-    println!("{}", filename);
+    if params.num_frames > 1 {
+        animate(params);
+    } else {
+        let num_entries = std::fs::read_dir(".").expect("Valid").count();
+        let image = draw(params);
+        let filename: String = format!("img-{}-{}.png", num_entries, size);
+        image.save(&filename).expect("Saved");
+        println!("{}", filename);
+    }
 }